@@ -0,0 +1,82 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt::{self, Display};
+
+/// This type is used internally as a shim over the standard library's
+/// `std::error::Error` trait and is used to specify the type returned in
+/// serde `Deserialize` and `Serialize` implementations.
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// This enum provides a custom error type for BCS serialization and deserialization.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    Io(core2::io::Error),
+    Custom(String),
+    ExceededMaxLen(usize),
+    ExceededContainerDepthLimit(&'static str),
+    ExpectedMapKey,
+    ExpectedMapValue,
+    MissingLen,
+    NotSupported(&'static str),
+    RemainingInput,
+    Eof,
+    /// A map contained two entries with equal serialized keys. Returned instead of
+    /// silently deduping by a serializer configured with
+    /// `Config::reject_duplicate_map_keys`.
+    DuplicateMapKey,
+    /// The wrapped error occurred at the given field path, e.g.
+    /// `struct Foo -> field bar -> seq[3]`. Attached by a serializer configured with
+    /// `Config::track_field_path`.
+    WithContext(String, Box<Error>),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::Custom(s) => write!(f, "{}", s),
+            Error::ExceededMaxLen(len) => {
+                write!(f, "sequence length exceeds the max length: {}", len)
+            }
+            Error::ExceededContainerDepthLimit(name) => {
+                write!(f, "exceeded max container depth while serializing {}", name)
+            }
+            Error::ExpectedMapKey => write!(f, "expected a map key"),
+            Error::ExpectedMapValue => write!(f, "expected a map value"),
+            Error::MissingLen => write!(f, "sequences must have a length"),
+            Error::NotSupported(what) => write!(f, "{} is not supported", what),
+            Error::RemainingInput => write!(f, "bytes remaining after deserialization"),
+            Error::Eof => write!(f, "unexpected end of input"),
+            Error::DuplicateMapKey => write!(f, "duplicate key in map"),
+            Error::WithContext(path, source) => write!(f, "{}: {}", path, source),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::WithContext(_, source) => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<core2::io::Error> for Error {
+    fn from(e: core2::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        use alloc::string::ToString;
+        Error::Custom(msg.to_string())
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod error;
+mod ser;
+
+pub use error::{Error, Result};
+pub use ser::{
+    is_human_readable, serialize_into, serialize_into_with_limit, serialized_size,
+    serialized_size_with_limit, to_bytes, to_bytes_with_limit, Config, Endianness,
+    LengthEncoding,
+};
+
+/// The maximum length of a sequence accepted by this crate.
+pub const MAX_SEQUENCE_LENGTH: usize = (1 << 31) - 1;
+
+/// The maximum container depth accepted by this crate.
+pub const MAX_CONTAINER_DEPTH: usize = 500;
@@ -1,6 +1,9 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
 use alloc::vec::Vec;
 use core2::io::{ErrorKind, Write, Result as core2Result};
 use crate::error::{Error, Result};
@@ -11,7 +14,8 @@ use serde::{ser, Serialize};
 /// Serialization can fail if `T`'s implementation of `Serialize` decides to
 /// fail, if `T` contains sequences which are longer than `MAX_SEQUENCE_LENGTH`,
 /// or if `T` attempts to serialize an unsupported datatype such as a f32,
-/// f64, or char.
+/// f64, or char. Use [`Config::allow_floats`]/[`Config::allow_char`] to opt
+/// into a non-canonical encoding for those types instead.
 ///
 /// # Examples
 ///
@@ -52,9 +56,7 @@ pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
 where
     T: ?Sized + Serialize,
 {
-    let mut output = Vec::new();
-    serialize_into(&mut output, value)?;
-    Ok(output)
+    Config::new().to_bytes(value)
 }
 
 /// Same as `to_bytes` but use `limit` as max container depth instead of MAX_CONTAINER_DEPTH
@@ -66,9 +68,7 @@ where
     if limit > crate::MAX_CONTAINER_DEPTH {
         return Err(Error::NotSupported("limit exceeds the max allowed depth"));
     }
-    let mut output = Vec::new();
-    serialize_into_with_limit(&mut output, value, limit)?;
-    Ok(output)
+    Config::new().max_container_depth(limit).to_bytes(value)
 }
 
 /// Same as `to_bytes` but write directly into an `std::io::Write` object.
@@ -77,8 +77,7 @@ where
     W: ?Sized + Write,
     T: ?Sized + Serialize,
 {
-    let serializer = Serializer::new(write, crate::MAX_CONTAINER_DEPTH);
-    value.serialize(serializer)
+    Config::new().serialize_into(write, value)
 }
 
 /// Same as `serialize_into` but use `limit` as max container depth instead of MAX_CONTAINER_DEPTH
@@ -91,8 +90,7 @@ where
     if limit > crate::MAX_CONTAINER_DEPTH {
         return Err(Error::NotSupported("limit exceeds the max allowed depth"));
     }
-    let serializer = Serializer::new(write, limit);
-    value.serialize(serializer)
+    Config::new().max_container_depth(limit).serialize_into(write, value)
 }
 
 struct WriteCounter(usize);
@@ -116,9 +114,7 @@ pub fn serialized_size<T>(value: &T) -> Result<usize>
 where
     T: ?Sized + Serialize,
 {
-    let mut counter = WriteCounter(0);
-    serialize_into(&mut counter, value)?;
-    Ok(counter.0)
+    Config::new().serialized_size(value)
 }
 
 /// Same as `serialized_size` but use `limit` as max container depth instead of MAX_CONTAINER_DEPTH
@@ -130,32 +126,277 @@ where
     if limit > crate::MAX_CONTAINER_DEPTH {
         return Err(Error::NotSupported("limit exceeds the max allowed depth"));
     }
-    let mut counter = WriteCounter(0);
-    serialize_into_with_limit(&mut counter, value, limit)?;
-    Ok(counter.0)
+    Config::new().max_container_depth(limit).serialized_size(value)
 }
 
 pub fn is_human_readable() -> bool {
     let mut output = Vec::new();
-    let serializer = Serializer::new(&mut output, crate::MAX_CONTAINER_DEPTH);
+    let serializer = Serializer::new(&mut output, Config::new(), None);
     ser::Serializer::is_human_readable(&serializer)
 }
 
+/// Configuration for a BCS serializer, analogous to bincode's `Options` builder.
+///
+/// A `Config` is built starting from [`Config::new`], which matches the crate-level
+/// defaults (canonical BCS), and customized with the builder methods below. It then
+/// drives serialization through [`Config::to_bytes`], [`Config::serialize_into`], and
+/// [`Config::serialized_size`], replacing the combinatorial `_with_limit` free
+/// functions with a single, composable entry point.
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    max_container_depth: usize,
+    max_sequence_length: usize,
+    human_readable: bool,
+    track_field_path: bool,
+    allow_floats: bool,
+    allow_char: bool,
+    reject_duplicate_map_keys: bool,
+    endianness: Endianness,
+    length_encoding: LengthEncoding,
+}
+
+impl Config {
+    /// Creates a new `Config` with the crate's canonical BCS defaults.
+    pub fn new() -> Self {
+        Self {
+            max_container_depth: crate::MAX_CONTAINER_DEPTH,
+            max_sequence_length: crate::MAX_SEQUENCE_LENGTH,
+            human_readable: false,
+            track_field_path: false,
+            allow_floats: false,
+            allow_char: false,
+            reject_duplicate_map_keys: false,
+            endianness: Endianness::Little,
+            length_encoding: LengthEncoding::Uleb128,
+        }
+    }
+
+    /// Sets the maximum container depth. Note that `limit` must not exceed
+    /// `MAX_CONTAINER_DEPTH`; a larger limit is rejected with `Error::NotSupported`
+    /// whenever this config is used to serialize.
+    pub fn max_container_depth(mut self, limit: usize) -> Self {
+        self.max_container_depth = limit;
+        self
+    }
+
+    /// Sets the maximum sequence length, in place of `MAX_SEQUENCE_LENGTH`.
+    pub fn max_sequence_length(mut self, limit: usize) -> Self {
+        self.max_sequence_length = limit;
+        self
+    }
+
+    /// Sets whether the resulting serializer reports itself as human readable via
+    /// `serde::Serializer::is_human_readable`. Defaults to `false` to preserve
+    /// canonical BCS bytes.
+    pub fn human_readable(mut self, human_readable: bool) -> Self {
+        self.human_readable = human_readable;
+        self
+    }
+
+    /// Sets whether a serialization error is annotated with the field path that led to
+    /// it, e.g. `struct Foo -> field bar -> seq[3]: sequence length exceeds the max length: 600`.
+    /// Defaults to `false` so the common case pays no bookkeeping cost.
+    pub fn track_field_path(mut self, track_field_path: bool) -> Self {
+        self.track_field_path = track_field_path;
+        self
+    }
+
+    /// Sets whether `f32`/`f64` are allowed to serialize, emitting their IEEE-754 bits
+    /// little-endian instead of returning `Error::NotSupported`. Defaults to `false`:
+    /// canonical BCS has no float representation, so this is only for non-consensus
+    /// use cases such as logging or local caches.
+    pub fn allow_floats(mut self, allow_floats: bool) -> Self {
+        self.allow_floats = allow_floats;
+        self
+    }
+
+    /// Sets whether `char` is allowed to serialize, emitting its `u32` code point
+    /// little-endian instead of returning `Error::NotSupported`. Defaults to `false`
+    /// for the same reason as `allow_floats`.
+    pub fn allow_char(mut self, allow_char: bool) -> Self {
+        self.allow_char = allow_char;
+        self
+    }
+
+    /// Sets whether a map containing two entries with equal serialized keys is
+    /// rejected with `Error::DuplicateMapKey`. Defaults to `false`, which preserves
+    /// the lenient behavior of silently dropping all but one entry per key; strict
+    /// mode is for consensus-critical callers that need a guarantee the map was
+    /// already canonical before encoding.
+    pub fn reject_duplicate_map_keys(mut self, reject_duplicate_map_keys: bool) -> Self {
+        self.reject_duplicate_map_keys = reject_duplicate_map_keys;
+        self
+    }
+
+    /// Sets the byte order used for multi-byte integers. Defaults to `Little`, which
+    /// is the only canonical BCS choice; `Big` is a non-canonical interop mode for
+    /// peers that speak a big-endian wire format, e.g. the Wormhole VAA serializer.
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets the encoding used for sequence lengths and enum variant indices.
+    /// Defaults to `Uleb128`, which is the only canonical BCS choice; `FixedU64` is a
+    /// non-canonical interop mode matching bincode's fixed-width length prefixes.
+    pub fn length_encoding(mut self, length_encoding: LengthEncoding) -> Self {
+        self.length_encoding = length_encoding;
+        self
+    }
+
+    /// Serializes the given data structure as a `Vec<u8>` of BCS, using this config.
+    pub fn to_bytes<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut output = Vec::new();
+        self.serialize_into(&mut output, value)?;
+        Ok(output)
+    }
+
+    /// Same as `to_bytes` but write directly into an `std::io::Write` object.
+    pub fn serialize_into<W, T>(&self, write: &mut W, value: &T) -> Result<()>
+    where
+        W: ?Sized + Write,
+        T: ?Sized + Serialize,
+    {
+        if self.max_container_depth > crate::MAX_CONTAINER_DEPTH {
+            return Err(Error::NotSupported("limit exceeds the max allowed depth"));
+        }
+        let mut path = Vec::new();
+        let path = if self.track_field_path {
+            Some(&mut path)
+        } else {
+            None
+        };
+        let serializer = Serializer::new(write, *self, path);
+        value.serialize(serializer)
+    }
+
+    /// Same as `to_bytes` but only returns the size of the serialized bytes.
+    pub fn serialized_size<T>(&self, value: &T) -> Result<usize>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut counter = WriteCounter(0);
+        self.serialize_into(&mut counter, value)?;
+        Ok(counter.0)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Byte order used when encoding multi-byte integers. Part of the non-canonical
+/// interop mode; canonical BCS always uses `Little`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// Encoding used for sequence lengths and enum variant indices. Part of the
+/// non-canonical interop mode; canonical BCS always uses `Uleb128`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthEncoding {
+    Uleb128,
+    FixedU64,
+}
+
+/// A single step on the path to the value that caused a serialization error, used to
+/// build a location like `struct Foo -> field bar -> seq[3]` (see
+/// [`Config::track_field_path`]).
+#[derive(Clone, Debug)]
+enum Segment {
+    Container(&'static str),
+    Variant(&'static str),
+    Field(&'static str),
+    Index(usize),
+    MapKey(usize),
+    MapValue(usize),
+}
+
+fn format_path(path: &[Segment]) -> String {
+    let mut message = String::new();
+    for (i, segment) in path.iter().enumerate() {
+        if i > 0 {
+            message.push_str(" -> ");
+        }
+        match segment {
+            Segment::Container(name) => message.push_str(&format!("struct {}", name)),
+            Segment::Variant(name) => message.push_str(&format!("variant {}", name)),
+            Segment::Field(name) => message.push_str(&format!("field {}", name)),
+            Segment::Index(index) => message.push_str(&format!("seq[{}]", index)),
+            Segment::MapKey(index) => message.push_str(&format!("map[{}].key", index)),
+            Segment::MapValue(index) => message.push_str(&format!("map[{}].value", index)),
+        }
+    }
+    message
+}
+
 /// Serialization implementation for BCS
 struct Serializer<'a, W: ?Sized> {
     output: &'a mut W,
-    max_remaining_depth: usize,
+    config: Config,
+    path: Option<&'a mut Vec<Segment>>,
+    index: usize,
 }
 
 impl<'a, W> Serializer<'a, W>
 where
     W: ?Sized + Write,
 {
-    /// Creates a new `Serializer` which will emit BCS.
-    fn new(output: &'a mut W, max_remaining_depth: usize) -> Self {
+    /// Creates a new `Serializer` which will emit BCS according to `config`, optionally
+    /// sharing a field-path stack with its parent for error context.
+    fn new(output: &'a mut W, config: Config, path: Option<&'a mut Vec<Segment>>) -> Self {
         Self {
             output,
-            max_remaining_depth,
+            config,
+            path,
+            index: 0,
+        }
+    }
+
+    fn push_segment(&mut self, segment: Segment) {
+        if let Some(path) = self.path.as_deref_mut() {
+            path.push(segment);
+        }
+    }
+
+    fn pop_segment(&mut self) {
+        if let Some(path) = self.path.as_deref_mut() {
+            path.pop();
+        }
+    }
+
+    /// Serializes `value` as a child of this serializer at `segment`, attaching the
+    /// accumulated field path to the first error returned, if any.
+    fn serialize_at<T>(&mut self, segment: Segment, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push_segment(segment);
+        let child = Serializer::new(self.output, self.config, self.path.as_deref_mut());
+        match value.serialize(child) {
+            Ok(()) => {
+                self.pop_segment();
+                Ok(())
+            }
+            Err(err) => Err(self.attach_path(err)),
+        }
+    }
+
+    /// Wraps `err` with the accumulated field path, unless it already carries one.
+    fn attach_path(&self, err: Error) -> Error {
+        match (&err, self.path.as_deref()) {
+            (Error::WithContext(..), _) => err,
+            (_, Some(path)) if !path.is_empty() => {
+                Error::WithContext(format_path(path), Box::new(err))
+            }
+            _ => err,
         }
     }
 
@@ -171,23 +412,40 @@ where
         Ok(())
     }
 
+    /// Writes a length prefix (sequence length or variant index) using the
+    /// configured length encoding: canonical ULEB128, or a bincode-compatible fixed
+    /// 8-byte length for the interop mode.
+    fn output_length_prefix(&mut self, value: u32) -> Result<()> {
+        match self.config.length_encoding {
+            LengthEncoding::Uleb128 => self.output_u32_as_uleb128(value),
+            LengthEncoding::FixedU64 => {
+                let bytes = match self.config.endianness {
+                    Endianness::Little => (value as u64).to_le_bytes(),
+                    Endianness::Big => (value as u64).to_be_bytes(),
+                };
+                self.output.write_all(&bytes).map_err(|e| Error::from(e))?;
+                Ok(())
+            }
+        }
+    }
+
     fn output_variant_index(&mut self, v: u32) -> Result<()> {
-        self.output_u32_as_uleb128(v)
+        self.output_length_prefix(v)
     }
 
     /// Serialize a sequence length as a u32.
     fn output_seq_len(&mut self, len: usize) -> Result<()> {
-        if len > crate::MAX_SEQUENCE_LENGTH {
+        if len > self.config.max_sequence_length {
             return Err(Error::ExceededMaxLen(len));
         }
-        self.output_u32_as_uleb128(len as u32)
+        self.output_length_prefix(len as u32)
     }
 
     fn enter_named_container(&mut self, name: &'static str) -> Result<()> {
-        if self.max_remaining_depth == 0 {
+        if self.config.max_container_depth == 0 {
             return Err(Error::ExceededContainerDepthLimit(name));
         }
-        self.max_remaining_depth -= 1;
+        self.config.max_container_depth -= 1;
         Ok(())
     }
 }
@@ -236,35 +494,66 @@ where
     }
 
     fn serialize_u16(self, v: u16) -> Result<()> {
-        self.output.write_all(&v.to_le_bytes()).map_err(|e| Error::from(e))?;
+        let bytes = match self.config.endianness {
+            Endianness::Little => v.to_le_bytes(),
+            Endianness::Big => v.to_be_bytes(),
+        };
+        self.output.write_all(&bytes).map_err(|e| Error::from(e))?;
         Ok(())
     }
 
     fn serialize_u32(self, v: u32) -> Result<()> {
-        self.output.write_all(&v.to_le_bytes()).map_err(|e| Error::from(e))?;
+        let bytes = match self.config.endianness {
+            Endianness::Little => v.to_le_bytes(),
+            Endianness::Big => v.to_be_bytes(),
+        };
+        self.output.write_all(&bytes).map_err(|e| Error::from(e))?;
         Ok(())
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.output.write_all(&v.to_le_bytes()).map_err(|e| Error::from(e))?;
+        let bytes = match self.config.endianness {
+            Endianness::Little => v.to_le_bytes(),
+            Endianness::Big => v.to_be_bytes(),
+        };
+        self.output.write_all(&bytes).map_err(|e| Error::from(e))?;
         Ok(())
     }
 
     fn serialize_u128(self, v: u128) -> Result<()> {
-        self.output.write_all(&v.to_le_bytes()).map_err(|e| Error::from(e))?;
+        let bytes = match self.config.endianness {
+            Endianness::Little => v.to_le_bytes(),
+            Endianness::Big => v.to_be_bytes(),
+        };
+        self.output.write_all(&bytes).map_err(|e| Error::from(e))?;
         Ok(())
     }
 
-    fn serialize_f32(self, _v: f32) -> Result<()> {
-        Err(Error::NotSupported("serialize_f32"))
+    // Always little-endian, regardless of the configured interop `endianness`: these
+    // are a non-canonical encoding to begin with, and bincode's own float/char layout
+    // is little-endian, so there is nothing to interop with on the big-endian side.
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        if !self.config.allow_floats {
+            return Err(Error::NotSupported("serialize_f32"));
+        }
+        self.output.write_all(&v.to_bits().to_le_bytes()).map_err(|e| Error::from(e))?;
+        Ok(())
     }
 
-    fn serialize_f64(self, _v: f64) -> Result<()> {
-        Err(Error::NotSupported("serialize_f64"))
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        if !self.config.allow_floats {
+            return Err(Error::NotSupported("serialize_f64"));
+        }
+        self.output.write_all(&v.to_bits().to_le_bytes()).map_err(|e| Error::from(e))?;
+        Ok(())
     }
 
-    fn serialize_char(self, _v: char) -> Result<()> {
-        Err(Error::NotSupported("serialize_char"))
+    fn serialize_char(self, v: char) -> Result<()> {
+        if !self.config.allow_char {
+            return Err(Error::NotSupported("serialize_char"));
+        }
+        self.output.write_all(&(v as u32).to_le_bytes()).map_err(|e| Error::from(e))?;
+        Ok(())
     }
 
     // Just serialize the string as a raw byte array
@@ -317,14 +606,14 @@ where
         T: ?Sized + Serialize,
     {
         self.enter_named_container(name)?;
-        value.serialize(self)
+        self.serialize_at(Segment::Container(name), value)
     }
 
     fn serialize_newtype_variant<T>(
         mut self,
         name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         value: &T,
     ) -> Result<()>
     where
@@ -332,7 +621,12 @@ where
     {
         self.enter_named_container(name)?;
         self.output_variant_index(variant_index)?;
-        value.serialize(self)
+        self.push_segment(Segment::Container(name));
+        let result = self.serialize_at(Segment::Variant(variant), value);
+        if result.is_ok() {
+            self.pop_segment();
+        }
+        result
     }
 
     // The start of the sequence, each value, and the end are three separate
@@ -359,6 +653,7 @@ where
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
         self.enter_named_container(name)?;
+        self.push_segment(Segment::Container(name));
         Ok(self)
     }
 
@@ -366,11 +661,13 @@ where
         mut self,
         name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
         self.enter_named_container(name)?;
         self.output_variant_index(variant_index)?;
+        self.push_segment(Segment::Container(name));
+        self.push_segment(Segment::Variant(variant));
         Ok(self)
     }
 
@@ -384,6 +681,7 @@ where
         _len: usize,
     ) -> Result<Self::SerializeStruct> {
         self.enter_named_container(name)?;
+        self.push_segment(Segment::Container(name));
         Ok(self)
     }
 
@@ -391,17 +689,21 @@ where
         mut self,
         name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
         self.enter_named_container(name)?;
         self.output_variant_index(variant_index)?;
+        self.push_segment(Segment::Container(name));
+        self.push_segment(Segment::Variant(variant));
         Ok(self)
     }
 
-    // BCS is not a human readable format
+    // BCS is not human readable by default, but a serializer configured with
+    // `Config::human_readable(true)` can opt in, e.g. to let `Serialize` impls that
+    // branch on this flag emit a debug/diagnostic encoding instead of canonical bytes.
     fn is_human_readable(&self) -> bool {
-        false
+        self.config.human_readable
     }
 }
 
@@ -416,7 +718,9 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(Serializer::new(self.output, self.max_remaining_depth))
+        let index = self.index;
+        self.index += 1;
+        self.serialize_at(Segment::Index(index), value)
     }
 
     fn end(self) -> Result<()> {
@@ -435,7 +739,9 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(Serializer::new(self.output, self.max_remaining_depth))
+        let index = self.index;
+        self.index += 1;
+        self.serialize_at(Segment::Index(index), value)
     }
 
     fn end(self) -> Result<()> {
@@ -454,10 +760,13 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(Serializer::new(self.output, self.max_remaining_depth))
+        let index = self.index;
+        self.index += 1;
+        self.serialize_at(Segment::Index(index), value)
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        self.pop_segment();
         Ok(())
     }
 }
@@ -473,10 +782,14 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(Serializer::new(self.output, self.max_remaining_depth))
+        let index = self.index;
+        self.index += 1;
+        self.serialize_at(Segment::Index(index), value)
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        self.pop_segment();
+        self.pop_segment();
         Ok(())
     }
 }
@@ -486,6 +799,7 @@ struct MapSerializer<'a, W: ?Sized> {
     serializer: Serializer<'a, W>,
     entries: Vec<(Vec<u8>, Vec<u8>)>,
     next_key: Option<Vec<u8>>,
+    entry_index: usize,
 }
 
 impl<'a, W: ?Sized> MapSerializer<'a, W> {
@@ -494,6 +808,7 @@ impl<'a, W: ?Sized> MapSerializer<'a, W> {
             serializer,
             entries: Vec::new(),
             next_key: None,
+            entry_index: 0,
         }
     }
 }
@@ -513,13 +828,21 @@ where
             return Err(Error::ExpectedMapValue);
         }
 
+        self.serializer.push_segment(Segment::MapKey(self.entry_index));
         let mut output = Vec::new();
-        key.serialize(Serializer::new(
+        let result = key.serialize(Serializer::new(
             &mut output,
-            self.serializer.max_remaining_depth,
-        ))?;
-        self.next_key = Some(output);
-        Ok(())
+            self.serializer.config,
+            self.serializer.path.as_deref_mut(),
+        ));
+        match result {
+            Ok(()) => {
+                self.serializer.pop_segment();
+                self.next_key = Some(output);
+                Ok(())
+            }
+            Err(err) => Err(self.serializer.attach_path(err)),
+        }
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
@@ -528,13 +851,22 @@ where
     {
         match self.next_key.take() {
             Some(key) => {
+                self.serializer.push_segment(Segment::MapValue(self.entry_index));
+                self.entry_index += 1;
                 let mut output = Vec::new();
-                value.serialize(Serializer::new(
+                let result = value.serialize(Serializer::new(
                     &mut output,
-                    self.serializer.max_remaining_depth,
-                ))?;
-                self.entries.push((key, output));
-                Ok(())
+                    self.serializer.config,
+                    self.serializer.path.as_deref_mut(),
+                ));
+                match result {
+                    Ok(()) => {
+                        self.serializer.pop_segment();
+                        self.entries.push((key, output));
+                        Ok(())
+                    }
+                    Err(err) => Err(self.serializer.attach_path(err)),
+                }
             }
             None => Err(Error::ExpectedMapKey),
         }
@@ -545,7 +877,13 @@ where
             return Err(Error::ExpectedMapValue);
         }
         self.entries.sort_by(|e1, e2| e1.0.cmp(&e2.0));
-        self.entries.dedup_by(|e1, e2| e1.0.eq(&e2.0));
+        if self.serializer.config.reject_duplicate_map_keys {
+            if self.entries.windows(2).any(|w| w[0].0 == w[1].0) {
+                return Err(Error::DuplicateMapKey);
+            }
+        } else {
+            self.entries.dedup_by(|e1, e2| e1.0.eq(&e2.0));
+        }
 
         let len = self.entries.len();
         self.serializer.output_seq_len(len)?;
@@ -566,14 +904,15 @@ where
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(Serializer::new(self.output, self.max_remaining_depth))
+        self.serialize_at(Segment::Field(key), value)
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        self.pop_segment();
         Ok(())
     }
 }
@@ -585,14 +924,118 @@ where
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T>(&mut self, _key: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(Serializer::new(self.output, self.max_remaining_depth))
+        self.serialize_at(Segment::Field(key), value)
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        self.pop_segment();
+        self.pop_segment();
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floats_and_char_are_not_supported_by_default() {
+        assert!(matches!(
+            Config::new().to_bytes(&1.0f32),
+            Err(Error::NotSupported("serialize_f32"))
+        ));
+        assert!(matches!(
+            Config::new().to_bytes(&1.0f64),
+            Err(Error::NotSupported("serialize_f64"))
+        ));
+        assert!(matches!(
+            Config::new().to_bytes(&'A'),
+            Err(Error::NotSupported("serialize_char"))
+        ));
+    }
+
+    #[test]
+    fn floats_and_char_encode_as_little_endian_when_allowed() {
+        let config = Config::new().allow_floats(true).allow_char(true);
+
+        assert_eq!(
+            config.to_bytes(&1.0f32).unwrap(),
+            1.0f32.to_bits().to_le_bytes().to_vec()
+        );
+        assert_eq!(
+            config.to_bytes(&1.0f64).unwrap(),
+            1.0f64.to_bits().to_le_bytes().to_vec()
+        );
+        assert_eq!(config.to_bytes(&'A').unwrap(), ('A' as u32).to_le_bytes().to_vec());
+    }
+
+    #[test]
+    fn floats_stay_little_endian_under_the_big_endian_interop_mode() {
+        let config = Config::new().allow_floats(true).endianness(Endianness::Big);
+
+        assert_eq!(
+            config.to_bytes(&1.0f32).unwrap(),
+            1.0f32.to_bits().to_le_bytes().to_vec()
+        );
+    }
+
+    /// A `Serialize` impl that deliberately emits two map entries with equal
+    /// serialized keys, which no real `Serialize` collection can produce on its own.
+    struct DuplicateKeyMap;
+
+    impl Serialize for DuplicateKeyMap {
+        fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+        where
+            S: ser::Serializer,
+        {
+            use ser::SerializeMap;
+
+            let mut map = serializer.serialize_map(Some(2))?;
+            map.serialize_entry("a", &1u8)?;
+            map.serialize_entry("a", &2u8)?;
+            map.end()
+        }
+    }
+
+    #[test]
+    fn duplicate_map_keys_are_rejected_when_configured() {
+        let config = Config::new().reject_duplicate_map_keys(true);
+
+        assert!(matches!(
+            config.to_bytes(&DuplicateKeyMap),
+            Err(Error::DuplicateMapKey)
+        ));
+    }
+
+    #[test]
+    fn duplicate_map_keys_are_deduped_to_the_first_entry_by_default() {
+        let config = Config::new();
+
+        // 1 entry (uleb128), key "a" (len-prefixed), then the *first* value (1), not
+        // the second (2): `Vec::dedup_by` keeps the first of each run of duplicates.
+        let expected = alloc::vec![1u8, 1, b'a', 1];
+        assert_eq!(config.to_bytes(&DuplicateKeyMap).unwrap(), expected);
+    }
+
+    #[test]
+    fn big_endian_interop_mode_encodes_integers_big_endian() {
+        let config = Config::new().endianness(Endianness::Big);
+
+        assert_eq!(config.to_bytes(&0x0102_0304u32).unwrap(), alloc::vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn fixed_u64_length_encoding_writes_an_eight_byte_prefix() {
+        let config = Config::new()
+            .endianness(Endianness::Big)
+            .length_encoding(LengthEncoding::FixedU64);
+
+        let value: Vec<u8> = alloc::vec![0xaa, 0xbb];
+        let expected = alloc::vec![0, 0, 0, 0, 0, 0, 0, 2, 0xaa, 0xbb];
+        assert_eq!(config.to_bytes(&value).unwrap(), expected);
+    }
+}